@@ -0,0 +1,304 @@
+use std::sync::Mutex;
+
+use rusqlite::Connection as SqliteConnection;
+use serde::{Deserialize, Serialize};
+use tiberius::{AuthMethod, Config, EncryptionLevel};
+
+/// A saved connection target. Everything except the password is plain data
+/// that lives in `profiles.db`; the password itself is kept out of that file
+/// and stored in the OS keychain, keyed by `id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConnectionProfile {
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+    pub username: String,
+    pub encryption: String,
+}
+
+const KEYCHAIN_SERVICE: &str = "project-maker-db";
+
+/// SQLite-backed store for connection profiles plus the id of whichever one
+/// is currently active. Guarded by a `Mutex` since `rusqlite::Connection`
+/// isn't `Sync`.
+pub struct ConnectionStore {
+    conn: Mutex<SqliteConnection>,
+}
+
+impl ConnectionStore {
+    pub fn open(db_path: &std::path::Path) -> rusqlite::Result<Self> {
+        let conn = SqliteConnection::open(db_path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS connection_profiles (
+                id         TEXT PRIMARY KEY,
+                name       TEXT NOT NULL,
+                host       TEXT NOT NULL,
+                port       INTEGER NOT NULL,
+                database   TEXT NOT NULL,
+                username   TEXT NOT NULL,
+                encryption TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS active_connection (
+                id    INTEGER PRIMARY KEY CHECK (id = 0),
+                profile_id TEXT NOT NULL
+             );",
+        )?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    pub fn add(&self, profile: &ConnectionProfile) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO connection_profiles (id, name, host, port, database, username, encryption)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                profile.id,
+                profile.name,
+                profile.host,
+                profile.port,
+                profile.database,
+                profile.username,
+                profile.encryption,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> rusqlite::Result<Vec<ConnectionProfile>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, host, port, database, username, encryption FROM connection_profiles",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ConnectionProfile {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                host: row.get(2)?,
+                port: row.get(3)?,
+                database: row.get(4)?,
+                username: row.get(5)?,
+                encryption: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn remove(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM connection_profiles WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> rusqlite::Result<Option<ConnectionProfile>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, name, host, port, database, username, encryption FROM connection_profiles WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(ConnectionProfile {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    host: row.get(2)?,
+                    port: row.get(3)?,
+                    database: row.get(4)?,
+                    username: row.get(5)?,
+                    encryption: row.get(6)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    pub fn set_active(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO active_connection (id, profile_id) VALUES (0, ?1)
+             ON CONFLICT(id) DO UPDATE SET profile_id = excluded.profile_id",
+            [id],
+        )?;
+        Ok(())
+    }
+
+    pub fn active_id(&self) -> rusqlite::Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT profile_id FROM active_connection WHERE id = 0", [], |row| row.get(0))
+            .optional()
+    }
+}
+
+use rusqlite::OptionalExtension;
+
+fn encryption_from_str(s: &str) -> EncryptionLevel {
+    match s {
+        "off" => EncryptionLevel::Off,
+        "on" => EncryptionLevel::On,
+        _ => EncryptionLevel::Required,
+    }
+}
+
+/// Builds a tiberius `Config` for `profile`, pulling the password out of the
+/// OS keychain rather than storing it alongside the rest of the profile.
+pub fn config_for_profile(profile: &ConnectionProfile) -> Result<Config, String> {
+    let password = keyring::Entry::new(KEYCHAIN_SERVICE, &profile.id)
+        .and_then(|entry| entry.get_password())
+        .map_err(|e| format!("failed to read password from keychain: {e}"))?;
+
+    let mut config = Config::new();
+    config.host(&profile.host);
+    config.port(profile.port);
+    config.database(&profile.database);
+    config.authentication(AuthMethod::sql_server(&profile.username, &password));
+    config.encryption(encryption_from_str(&profile.encryption));
+    config.trust_cert();
+    Ok(config)
+}
+
+pub fn save_password(profile_id: &str, password: &str) -> Result<(), String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, profile_id)
+        .map_err(|e| format!("failed to open keychain entry: {e}"))?;
+    entry.set_password(password).map_err(|e| format!("failed to save password to keychain: {e}"))
+}
+
+fn delete_password(profile_id: &str) -> Result<(), String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, profile_id)
+        .map_err(|e| format!("failed to open keychain entry: {e}"))?;
+    match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("failed to delete password from keychain: {e}")),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewConnectionRequest {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+    pub username: String,
+    pub password: String,
+    pub encryption: String,
+}
+
+#[tauri::command]
+pub fn add_connection(
+    store: tauri::State<'_, ConnectionStore>,
+    request: NewConnectionRequest,
+) -> Result<ConnectionProfile, String> {
+    let profile = ConnectionProfile {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name,
+        host: request.host,
+        port: request.port,
+        database: request.database,
+        username: request.username,
+        encryption: request.encryption,
+    };
+
+    save_password(&profile.id, &request.password)?;
+    store.add(&profile).map_err(|e| e.to_string())?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn list_connections(store: tauri::State<'_, ConnectionStore>) -> Result<Vec<ConnectionProfile>, String> {
+    store.list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_connection(store: tauri::State<'_, ConnectionStore>, id: String) -> Result<(), String> {
+    store.remove(&id).map_err(|e| e.to_string())?;
+    delete_password(&id)
+}
+
+#[tauri::command]
+pub async fn set_active_connection(
+    store: tauri::State<'_, ConnectionStore>,
+    db: tauri::State<'_, crate::db::DbState>,
+    id: String,
+) -> Result<(), String> {
+    let profile = store
+        .get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("no connection profile with id {id}"))?;
+
+    let config = config_for_profile(&profile)?;
+    db.set_config(config).await?;
+    store.set_active(&id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_profile(id: &str) -> ConnectionProfile {
+        ConnectionProfile {
+            id: id.to_string(),
+            name: "Staging".to_string(),
+            host: "staging.example.com".to_string(),
+            port: 1433,
+            database: "app".to_string(),
+            username: "app_user".to_string(),
+            encryption: "required".to_string(),
+        }
+    }
+
+    #[test]
+    fn add_list_and_get_round_trip_a_profile() {
+        let store = ConnectionStore::open(std::path::Path::new(":memory:")).unwrap();
+        let profile = sample_profile("profile-1");
+        store.add(&profile).unwrap();
+
+        let listed = store.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, "profile-1");
+
+        let fetched = store.get("profile-1").unwrap();
+        assert_eq!(fetched.unwrap().host, "staging.example.com");
+        assert!(store.get("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn add_with_same_id_replaces_the_existing_profile() {
+        let store = ConnectionStore::open(std::path::Path::new(":memory:")).unwrap();
+        store.add(&sample_profile("profile-1")).unwrap();
+
+        let mut updated = sample_profile("profile-1");
+        updated.name = "Production".to_string();
+        store.add(&updated).unwrap();
+
+        let listed = store.list().unwrap();
+        assert_eq!(listed.len(), 1, "re-adding the same id should replace, not duplicate");
+        assert_eq!(listed[0].name, "Production");
+    }
+
+    #[test]
+    fn remove_deletes_the_profile() {
+        let store = ConnectionStore::open(std::path::Path::new(":memory:")).unwrap();
+        store.add(&sample_profile("profile-1")).unwrap();
+        store.remove("profile-1").unwrap();
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn active_id_tracks_the_most_recent_set_active_call() {
+        let store = ConnectionStore::open(std::path::Path::new(":memory:")).unwrap();
+        store.add(&sample_profile("profile-1")).unwrap();
+        store.add(&sample_profile("profile-2")).unwrap();
+        assert_eq!(store.active_id().unwrap(), None);
+
+        store.set_active("profile-1").unwrap();
+        assert_eq!(store.active_id().unwrap(), Some("profile-1".to_string()));
+
+        store.set_active("profile-2").unwrap();
+        assert_eq!(store.active_id().unwrap(), Some("profile-2".to_string()));
+    }
+
+    #[test]
+    fn encryption_from_str_defaults_to_required_for_unknown_values() {
+        assert!(matches!(encryption_from_str("off"), EncryptionLevel::Off));
+        assert!(matches!(encryption_from_str("on"), EncryptionLevel::On));
+        assert!(matches!(encryption_from_str("whatever"), EncryptionLevel::Required));
+    }
+}
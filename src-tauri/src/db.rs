@@ -1,12 +1,318 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bb8::PooledConnection;
+use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
-use tiberius::{Client, Config, AuthMethod, ColumnData, ToSql, QueryItem};
+use tiberius::{Client, Config, AuthMethod, ColumnData, ColumnType, ToSql, QueryItem};
 use tokio::net::TcpStream;
 use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
 use futures_util::stream::TryStreamExt;
 use serde_json::{Map, Value};
 
+pub type DbClient = Client<Compat<TcpStream>>;
+pub type DbPool = bb8::Pool<SqlConnectionManager>;
+
+/// `bb8::ManageConnection` impl that opens a fresh tiberius connection
+/// (TCP connect + TLS + login) per pooled slot, and treats a tiberius
+/// connection error as grounds for recycling the slot.
+#[derive(Clone)]
+pub struct SqlConnectionManager {
+    config: Config,
+}
+
+impl SqlConnectionManager {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait::async_trait]
+impl bb8::ManageConnection for SqlConnectionManager {
+    type Connection = DbClient;
+    type Error = tiberius::error::Error;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        connect(&self.config).await
+    }
+
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        conn.simple_query("SELECT 1").await?.into_results().await?;
+        Ok(())
+    }
+
+    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
+        false
+    }
+}
+
+pub type TxId = String;
+
+/// A connection pinned to an open `BEGIN TRAN`, keyed by `TxId` in
+/// `DbState::transactions`. Dropping the handle without an explicit commit
+/// rolls back and releases the connection back to the pool, so a crashed
+/// frontend can't leak an open transaction — but nothing drops it until
+/// something removes its entry from the map, which is why `last_used` and
+/// `spawn_transaction_reaper` below exist: without them an abandoned tx_id
+/// (the frontend just stops calling commit/rollback) sits in the map
+/// forever, holding a pool connection hostage.
+struct TransactionHandle {
+    client: Option<PooledConnection<'static, SqlConnectionManager>>,
+    last_used: std::time::Instant,
+}
+
+impl Drop for TransactionHandle {
+    fn drop(&mut self) {
+        if let Some(mut client) = self.client.take() {
+            tauri::async_runtime::spawn(async move {
+                let result = async {
+                    client.simple_query("ROLLBACK TRAN").await?.into_results().await
+                }
+                .await;
+                if let Err(e) = result {
+                    println!("[Rust DB] Rollback-on-drop failed: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// One lock per open transaction, so a slow statement on transaction A only
+/// blocks other statements on A — not `begin_transaction`/`commit_transaction`
+/// /`rollback_transaction`, nor statements on an unrelated transaction B.
+/// The outer `DbState::transactions` mutex only ever guards the map's shape
+/// (insert/remove/lookup), never a query in flight.
+type TransactionSlot = std::sync::Arc<tokio::sync::Mutex<TransactionHandle>>;
+
+/// Shared handle to the transaction map, cloned into `spawn_transaction_reaper`
+/// so the sweep task can outlive the `DbState::new` call that starts it.
+type TransactionMap = std::sync::Arc<tokio::sync::Mutex<HashMap<TxId, TransactionSlot>>>;
+
+/// How long a transaction can go untouched (no `execute_sql`/`query_sql`/etc.
+/// against its `tx_id`) before the background reaper rolls it back and frees
+/// its pool connection.
+const TRANSACTION_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How often the background reaper scans for stale transactions.
+const TRANSACTION_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Holds the pool for whichever connection profile is currently active.
+/// `pool` is behind an `RwLock` so `set_active_connection` can swap it out
+/// at runtime without restarting the app.
 pub struct DbState {
-    pub config: Config,
+    pool: RwLock<DbPool>,
+    pub pool_size: u32,
+    pub idle_timeout: Duration,
+    transactions: TransactionMap,
+}
+
+impl DbState {
+    pub async fn new(config: Config, pool_size: u32, idle_timeout: Duration) -> Result<Self, tiberius::error::Error> {
+        let pool = Self::build_pool(config, pool_size, idle_timeout).await?;
+        let transactions: TransactionMap = std::sync::Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        spawn_transaction_reaper(transactions.clone());
+        Ok(Self {
+            pool: RwLock::new(pool),
+            pool_size,
+            idle_timeout,
+            transactions,
+        })
+    }
+
+    async fn build_pool(config: Config, pool_size: u32, idle_timeout: Duration) -> Result<DbPool, tiberius::error::Error> {
+        let manager = SqlConnectionManager::new(config);
+        bb8::Pool::builder()
+            .max_size(pool_size)
+            .idle_timeout(Some(idle_timeout))
+            .build(manager)
+            .await
+            .map_err(|e| tiberius::error::Error::Io {
+                kind: std::io::ErrorKind::Other,
+                message: e.to_string(),
+            })
+    }
+
+    pub async fn get(&self) -> Result<PooledConnection<'static, SqlConnectionManager>, String> {
+        self.pool.read().await.get_owned().await.map_err(|e| {
+            println!("[Rust DB] Pool checkout error: {}", e);
+            e.to_string()
+        })
+    }
+
+    /// Replace the active pool with one built from `config`, e.g. after the
+    /// frontend switches the active connection profile.
+    pub async fn set_config(&self, config: Config) -> Result<(), String> {
+        let new_pool = Self::build_pool(config, self.pool_size, self.idle_timeout)
+            .await
+            .map_err(|e| e.to_string())?;
+        *self.pool.write().await = new_pool;
+        Ok(())
+    }
+}
+
+/// One sweep over `transactions`: rolls back and evicts any entry whose
+/// `last_used` is older than `ttl`. Split out from `spawn_transaction_reaper`
+/// so a single pass can be driven directly in a test instead of waiting on
+/// the sweep interval.
+///
+/// A slot is only reaped if it isn't currently locked (`try_lock`) — a
+/// transaction mid-`execute_sql` is, by definition, not abandoned, even if
+/// it's been open longer than `ttl`.
+async fn reap_stale_transactions(transactions: &TransactionMap, ttl: Duration) {
+    // The staleness check and the removal from the map happen in one critical
+    // section, with the map lock held throughout. Checking staleness, then
+    // dropping the map lock before removing, would let `resolve_client` grab
+    // the slot, refresh `last_used`, and run a query to completion in the gap
+    // — only for the reaper to then remove and roll back a transaction that
+    // was just legitimately in use.
+    let to_rollback: Vec<(TxId, PooledConnection<'static, SqlConnectionManager>)> = {
+        let mut map = transactions.lock().await;
+        let stale_ids: Vec<TxId> = map
+            .iter()
+            .filter_map(|(id, slot)| {
+                let handle = slot.try_lock().ok()?;
+                (handle.last_used.elapsed() >= ttl).then(|| id.clone())
+            })
+            .collect();
+
+        stale_ids
+            .into_iter()
+            .filter_map(|id| {
+                let slot = map.remove(&id)?;
+                let client = slot.try_lock().ok()?.client.take()?;
+                Some((id, client))
+            })
+            .collect()
+    };
+
+    for (id, mut client) in to_rollback {
+        println!("[Rust DB] Reaping abandoned transaction {} (idle >= {:?})", id, ttl);
+        let result = async { client.simple_query("ROLLBACK TRAN").await?.into_results().await }.await;
+        if let Err(e) = result {
+            println!("[Rust DB] Reap rollback failed for {}: {}", id, e);
+        }
+    }
+}
+
+/// Background sweep that reaps abandoned transactions on a timer, so a
+/// crashed or disconnected frontend that never calls `commit_transaction`/
+/// `rollback_transaction` doesn't wedge the pool forever. Runs for the
+/// lifetime of the app; there's one of these per `DbState`.
+fn spawn_transaction_reaper(transactions: TransactionMap) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TRANSACTION_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            reap_stale_transactions(&transactions, TRANSACTION_TTL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn begin_transaction(state: tauri::State<'_, DbState>) -> Result<TxId, String> {
+    let mut client = state.get().await?;
+    client
+        .simple_query("BEGIN TRAN")
+        .await
+        .map_err(|e| e.to_string())?
+        .into_results()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let tx_id = uuid::Uuid::new_v4().to_string();
+    let slot: TransactionSlot = std::sync::Arc::new(tokio::sync::Mutex::new(TransactionHandle {
+        client: Some(client),
+        last_used: std::time::Instant::now(),
+    }));
+    state.transactions.lock().await.insert(tx_id.clone(), slot);
+    println!("[Rust DB] Began transaction {}", tx_id);
+    Ok(tx_id)
+}
+
+/// Resolves to either a connection pinned to an open transaction or a fresh
+/// one checked out of the pool, so `execute_sql`/`query_sql` can share one
+/// code path regardless of whether a `tx_id` was supplied. The `Pinned`
+/// guard is on that transaction's own slot lock, not the map lock, so it
+/// never blocks other transactions.
+enum ClientHandle {
+    Pooled(PooledConnection<'static, SqlConnectionManager>),
+    Pinned(tokio::sync::OwnedMutexGuard<TransactionHandle>),
+}
+
+impl ClientHandle {
+    fn client(&mut self) -> Result<&mut DbClient, String> {
+        match self {
+            ClientHandle::Pooled(client) => Ok(&mut **client),
+            ClientHandle::Pinned(handle) => handle
+                .client
+                .as_mut()
+                .map(|client| &mut **client)
+                .ok_or_else(|| "transaction connection already released".to_string()),
+        }
+    }
+}
+
+async fn resolve_client(state: &tauri::State<'_, DbState>, tx_id: &Option<TxId>) -> Result<ClientHandle, String> {
+    match tx_id {
+        Some(id) => {
+            let slot = state
+                .transactions
+                .lock()
+                .await
+                .get(id)
+                .cloned()
+                .ok_or_else(|| format!("unknown transaction {id}"))?;
+            let mut guard = slot.lock_owned().await;
+            guard.last_used = std::time::Instant::now();
+            Ok(ClientHandle::Pinned(guard))
+        }
+        None => Ok(ClientHandle::Pooled(state.get().await?)),
+    }
+}
+
+async fn take_transaction_client(
+    state: &tauri::State<'_, DbState>,
+    tx_id: &str,
+) -> Result<PooledConnection<'static, SqlConnectionManager>, String> {
+    let slot = state
+        .transactions
+        .lock()
+        .await
+        .remove(tx_id)
+        .ok_or_else(|| format!("unknown transaction {tx_id}"))?;
+    slot.lock()
+        .await
+        .client
+        .take()
+        .ok_or_else(|| format!("transaction {tx_id} connection already released"))
+}
+
+#[tauri::command]
+pub async fn commit_transaction(state: tauri::State<'_, DbState>, tx_id: TxId) -> Result<(), String> {
+    let mut client = take_transaction_client(&state, &tx_id).await?;
+    client
+        .simple_query("COMMIT TRAN")
+        .await
+        .map_err(|e| e.to_string())?
+        .into_results()
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("[Rust DB] Committed transaction {}", tx_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn rollback_transaction(state: tauri::State<'_, DbState>, tx_id: TxId) -> Result<(), String> {
+    let mut client = take_transaction_client(&state, &tx_id).await?;
+    client
+        .simple_query("ROLLBACK TRAN")
+        .await
+        .map_err(|e| e.to_string())?
+        .into_results()
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("[Rust DB] Rolled back transaction {}", tx_id);
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -14,7 +320,7 @@ pub struct QueryResult {
     pub rows: Vec<Value>,
 }
 
-pub async fn connect(config: &Config) -> Result<Client<Compat<TcpStream>>, tiberius::error::Error> {
+pub async fn connect(config: &Config) -> Result<DbClient, tiberius::error::Error> {
     println!("[Rust DB] Connecting to: {}", config.get_addr());
     let tcp = TcpStream::connect(config.get_addr()).await?;
     tcp.set_nodelay(true)?;
@@ -24,6 +330,9 @@ pub async fn connect(config: &Config) -> Result<Client<Compat<TcpStream>>, tiber
     Ok(client)
 }
 
+/// ISO-8601 with microseconds, the precision SQL Server's datetime2 can hold.
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6f";
+
 fn column_to_json(data: ColumnData<'_>) -> Value {
     match data {
         ColumnData::Binary(Some(b)) => Value::String(hex::encode(b)),
@@ -36,12 +345,102 @@ fn column_to_json(data: ColumnData<'_>) -> Value {
         ColumnData::F64(Some(f)) => serde_json::json!(f),
         ColumnData::String(Some(s)) => Value::String(s.to_string()),
         ColumnData::Guid(Some(g)) => Value::String(g.to_string()),
+        // Numeric/Decimal: render as a decimal string so money values don't
+        // lose precision going through an f64.
+        ColumnData::Numeric(Some(n)) => Value::String(n.to_string()),
+        ColumnData::DateTime(Some(dt)) => {
+            let naive: chrono::NaiveDateTime = dt.into();
+            Value::String(naive.format(DATETIME_FORMAT).to_string())
+        }
+        ColumnData::SmallDateTime(Some(dt)) => {
+            let naive: chrono::NaiveDateTime = dt.into();
+            Value::String(naive.format(DATETIME_FORMAT).to_string())
+        }
+        ColumnData::DateTime2(Some(dt)) => {
+            let naive: chrono::NaiveDateTime = dt.into();
+            Value::String(naive.format(DATETIME_FORMAT).to_string())
+        }
+        ColumnData::Date(Some(d)) => {
+            let naive: chrono::NaiveDate = d.into();
+            Value::String(naive.format("%Y-%m-%d").to_string())
+        }
+        ColumnData::Time(Some(t)) => {
+            let naive: chrono::NaiveTime = t.into();
+            Value::String(naive.format("%H:%M:%S%.6f").to_string())
+        }
+        ColumnData::DateTimeOffset(Some(dto)) => {
+            let dt: chrono::DateTime<chrono::Utc> = dto.into();
+            Value::String(dt.to_rfc3339())
+        }
+        ColumnData::Xml(Some(xml)) => Value::String(xml.to_string()),
         _ => Value::Null,
     }
 }
 
+/// Heuristic: a plain JSON string binds as a SQL `uniqueidentifier` if it
+/// parses as a GUID. Known false positive: an ordinary GUID-*shaped* string
+/// that's meant to bind as text (e.g. a user-entered order number or SKU
+/// stored in a `varchar` column) gets silently coerced to `Uuid` too, which
+/// then fails or mis-binds against a non-`uniqueidentifier` column. Callers
+/// that can't guarantee their string params are never GUID-shaped should
+/// pass `{"type": "string", "value": "..."}` instead of a bare string — see
+/// `json_to_sql_param`.
+fn looks_like_guid(s: &str) -> bool {
+    uuid::Uuid::parse_str(s).is_ok()
+}
+
+/// Same trade-off as `looks_like_guid`, for ISO-8601/RFC3339-shaped strings
+/// binding as `datetime2`/`datetimeoffset` instead of text.
+fn looks_like_iso_datetime(s: &str) -> bool {
+    chrono::NaiveDateTime::parse_from_str(s, DATETIME_FORMAT).is_ok()
+        || chrono::DateTime::parse_from_rfc3339(s).is_ok()
+}
+
+/// Binds a JSON param to a tiberius `ToSql` value. Plain scalars (string,
+/// number, bool, null) go through the heuristics above. When a caller needs
+/// to bypass those heuristics — most commonly to force a GUID- or
+/// datetime-shaped value to bind as plain text — they can instead pass an
+/// explicit type hint: `{"type": "string" | "guid" | "datetime", "value": ...}`.
+/// A hint with a `value` that doesn't actually match `type` binds as SQL
+/// `NULL` rather than panicking, the same way an unrecognized `Value`
+/// variant does below.
 fn json_to_sql_param(val: &Value) -> Box<dyn ToSql + Sync> {
+    if let Value::Object(hint) = val {
+        if let Some(Value::String(ty)) = hint.get("type") {
+            let value = hint.get("value").and_then(Value::as_str);
+            let inner = value.unwrap_or_default();
+            return match ty.as_str() {
+                "string" => match value {
+                    Some(s) => Box::new(s.to_string()),
+                    None => Box::new(Option::<String>::None),
+                },
+                "guid" => match uuid::Uuid::parse_str(inner) {
+                    Ok(guid) => Box::new(guid),
+                    Err(_) => Box::new(Option::<String>::None),
+                },
+                "datetime" => {
+                    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(inner) {
+                        Box::new(dt.with_timezone(&chrono::Utc).fixed_offset())
+                    } else if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(inner, DATETIME_FORMAT) {
+                        Box::new(naive)
+                    } else {
+                        Box::new(Option::<String>::None)
+                    }
+                }
+                _ => Box::new(Option::<String>::None),
+            };
+        }
+    }
+
     match val {
+        Value::String(s) if looks_like_guid(s) => Box::new(uuid::Uuid::parse_str(s).unwrap()),
+        Value::String(s) if looks_like_iso_datetime(s) => {
+            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+                Box::new(dt.with_timezone(&chrono::Utc).fixed_offset())
+            } else {
+                Box::new(chrono::NaiveDateTime::parse_from_str(s, DATETIME_FORMAT).unwrap())
+            }
+        }
         Value::String(s) => Box::new(s.clone()),
         Value::Number(n) => {
             if let Some(i) = n.as_i64() {
@@ -58,13 +457,16 @@ fn json_to_sql_param(val: &Value) -> Box<dyn ToSql + Sync> {
 }
 
 #[tauri::command]
-pub async fn execute_sql(state: tauri::State<'_, DbState>, sql: String, params: Vec<Value>) -> Result<u64, String> {
+pub async fn execute_sql(
+    state: tauri::State<'_, DbState>,
+    sql: String,
+    params: Vec<Value>,
+    tx_id: Option<TxId>,
+) -> Result<u64, String> {
     println!("[Rust DB] Executing SQL: {}", sql);
-    let mut client = connect(&state.config).await.map_err(|e| {
-        println!("[Rust DB] Connect error: {}", e);
-        e.to_string()
-    })?;
-    
+    let mut handle = resolve_client(&state, &tx_id).await?;
+    let client = handle.client()?;
+
     let mut processed_sql = sql.clone();
     for i in 1..=params.len() {
         processed_sql = processed_sql.replacen('?', &format!("@p{}", i), 1);
@@ -77,20 +479,91 @@ pub async fn execute_sql(state: tauri::State<'_, DbState>, sql: String, params:
         println!("[Rust DB] Execute error: {}", e);
         e.to_string()
     })?;
-    
+
     let affected = res.rows_affected().first().cloned().unwrap_or(0);
     println!("[Rust DB] Rows affected: {}", affected);
     Ok(affected)
 }
 
 #[tauri::command]
-pub async fn query_sql(state: tauri::State<'_, DbState>, sql: String, params: Vec<Value>) -> Result<Value, String> {
+pub async fn query_sql(
+    state: tauri::State<'_, DbState>,
+    sql: String,
+    params: Vec<Value>,
+    tx_id: Option<TxId>,
+) -> Result<Value, String> {
     println!("[Rust DB] Querying SQL: {}", sql);
-    let mut client = connect(&state.config).await.map_err(|e| {
-        println!("[Rust DB] Connect error: {}", e);
+    let mut handle = resolve_client(&state, &tx_id).await?;
+    let client = handle.client()?;
+
+    let mut processed_sql = sql.clone();
+    for i in 1..=params.len() {
+        processed_sql = processed_sql.replacen('?', &format!("@p{}", i), 1);
+    }
+
+    let sql_params: Vec<Box<dyn ToSql + Sync>> = params.iter().map(json_to_sql_param).collect();
+    let ref_params: Vec<&dyn ToSql> = sql_params.iter().map(|p| p.as_ref() as &dyn ToSql).collect();
+
+    let mut stream = client.query(processed_sql, &ref_params).await.map_err(|e| {
+        println!("[Rust DB] Query error: {}", e);
         e.to_string()
     })?;
-    
+
+    let mut rows = Vec::new();
+
+    while let Some(item) = stream.try_next().await.map_err(|e| {
+        println!("[Rust DB] Stream error: {}", e);
+        e.to_string()
+    })? {
+        if let QueryItem::Row(row) = item {
+            rows.push(row_to_json(row));
+        }
+    }
+
+    println!("[Rust DB] Rows returned: {}", rows.len());
+    Ok(Value::Array(rows))
+}
+
+fn row_to_json(row: tiberius::Row) -> Value {
+    let mut map = Map::new();
+    let col_names: Vec<String> = row.columns().iter().map(|c| c.name().to_string()).collect();
+    for (name, data) in col_names.into_iter().zip(row.into_iter()) {
+        map.insert(name, column_to_json(data));
+    }
+    Value::Object(map)
+}
+
+const DEFAULT_STREAM_BATCH_SIZE: usize = 500;
+
+/// Resolves the frontend-supplied batch size to a usable one: falls back to
+/// `DEFAULT_STREAM_BATCH_SIZE` when unset, and floors at 1 so a `0` from the
+/// frontend can't turn the stream into a batch that's never flushed.
+fn normalize_batch_size(batch_size: Option<usize>) -> usize {
+    batch_size.unwrap_or(DEFAULT_STREAM_BATCH_SIZE).max(1)
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum QueryStreamMessage {
+    Rows { rows: Vec<Value> },
+    Done { total: usize },
+}
+
+/// Like `query_sql`, but pushes rows to the frontend in batches over `channel`
+/// as they're drained from the `QueryStream` instead of buffering the whole
+/// result set, so large result sets don't block the UI or blow up memory.
+#[tauri::command]
+pub async fn query_sql_stream(
+    state: tauri::State<'_, DbState>,
+    sql: String,
+    params: Vec<Value>,
+    batch_size: Option<usize>,
+    channel: tauri::ipc::Channel<QueryStreamMessage>,
+) -> Result<(), String> {
+    println!("[Rust DB] Streaming query: {}", sql);
+    let batch_size = normalize_batch_size(batch_size);
+    let mut client = state.get().await?;
+
     let mut processed_sql = sql.clone();
     for i in 1..=params.len() {
         processed_sql = processed_sql.replacen('?', &format!("@p{}", i), 1);
@@ -103,26 +576,448 @@ pub async fn query_sql(state: tauri::State<'_, DbState>, sql: String, params: Ve
         println!("[Rust DB] Query error: {}", e);
         e.to_string()
     })?;
-    
-    let mut rows = Vec::new();
+
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut total = 0usize;
+
+    while let Some(item) = stream.try_next().await.map_err(|e| {
+        println!("[Rust DB] Stream error: {}", e);
+        e.to_string()
+    })? {
+        if let QueryItem::Row(row) = item {
+            batch.push(row_to_json(row));
+            total += 1;
+
+            if batch.len() >= batch_size {
+                channel
+                    .send(QueryStreamMessage::Rows { rows: std::mem::take(&mut batch) })
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        channel.send(QueryStreamMessage::Rows { rows: batch }).map_err(|e| e.to_string())?;
+    }
+
+    println!("[Rust DB] Streamed {} total rows", total);
+    channel.send(QueryStreamMessage::Done { total }).map_err(|e| e.to_string())
+}
+
+/// Per-column Arrow array builder. Numeric/bit columns get a typed Arrow
+/// array; everything else (decimal, date/time, guid, xml, binary, ...) is
+/// rendered through `column_to_json` and stored as Utf8, matching how those
+/// types are already serialized for `query_sql`.
+enum ArrowColumnBuilder {
+    Boolean(arrow::array::BooleanBuilder),
+    Int64(arrow::array::Int64Builder),
+    Float64(arrow::array::Float64Builder),
+    Utf8(arrow::array::StringBuilder),
+}
+
+impl ArrowColumnBuilder {
+    fn for_column(sample: &ColumnData<'_>) -> Self {
+        match sample {
+            ColumnData::Bit(_) => Self::Boolean(arrow::array::BooleanBuilder::new()),
+            ColumnData::U8(_) | ColumnData::I16(_) | ColumnData::I32(_) | ColumnData::I64(_) => {
+                Self::Int64(arrow::array::Int64Builder::new())
+            }
+            ColumnData::F32(_) | ColumnData::F64(_) => Self::Float64(arrow::array::Float64Builder::new()),
+            _ => Self::Utf8(arrow::array::StringBuilder::new()),
+        }
+    }
+
+    /// Same classification as `for_column`, but from a `tiberius::ColumnType`
+    /// (the type tiberius hands back in `QueryItem::Metadata`, ahead of any
+    /// rows) rather than a sampled `ColumnData` value. Lets `query_sql_arrow`
+    /// build the right-shaped schema even for a query that returns zero rows.
+    fn for_column_type(ty: &ColumnType) -> Self {
+        match ty {
+            ColumnType::Bit | ColumnType::Bitn => Self::Boolean(arrow::array::BooleanBuilder::new()),
+            ColumnType::Int1 | ColumnType::Int2 | ColumnType::Int4 | ColumnType::Int8 | ColumnType::Intn => {
+                Self::Int64(arrow::array::Int64Builder::new())
+            }
+            ColumnType::Float4 | ColumnType::Float8 | ColumnType::Floatn => {
+                Self::Float64(arrow::array::Float64Builder::new())
+            }
+            _ => Self::Utf8(arrow::array::StringBuilder::new()),
+        }
+    }
+
+    fn data_type(&self) -> arrow::datatypes::DataType {
+        match self {
+            Self::Boolean(_) => arrow::datatypes::DataType::Boolean,
+            Self::Int64(_) => arrow::datatypes::DataType::Int64,
+            Self::Float64(_) => arrow::datatypes::DataType::Float64,
+            Self::Utf8(_) => arrow::datatypes::DataType::Utf8,
+        }
+    }
+
+    fn append(&mut self, data: ColumnData<'_>) {
+        match (self, data) {
+            (Self::Boolean(b), ColumnData::Bit(v)) => b.append_option(v),
+            (Self::Int64(b), ColumnData::U8(v)) => b.append_option(v.map(i64::from)),
+            (Self::Int64(b), ColumnData::I16(v)) => b.append_option(v.map(i64::from)),
+            (Self::Int64(b), ColumnData::I32(v)) => b.append_option(v.map(i64::from)),
+            (Self::Int64(b), ColumnData::I64(v)) => b.append_option(v),
+            (Self::Float64(b), ColumnData::F32(v)) => b.append_option(v.map(f64::from)),
+            (Self::Float64(b), ColumnData::F64(v)) => b.append_option(v),
+            (Self::Utf8(b), other) => match column_to_json(other) {
+                Value::String(s) => b.append_value(s),
+                Value::Null => b.append_null(),
+                other => b.append_value(other.to_string()),
+            },
+            // A row produced a different variant than the column's builder
+            // was chosen for; store NULL in its place rather than panic, so
+            // the column keeps its length and one odd row doesn't fail the
+            // whole batch.
+            (this, _) => match this {
+                Self::Boolean(b) => b.append_null(),
+                Self::Int64(b) => b.append_null(),
+                Self::Float64(b) => b.append_null(),
+                Self::Utf8(b) => b.append_null(),
+            },
+        }
+    }
+
+    fn finish(self) -> arrow::array::ArrayRef {
+        match self {
+            Self::Boolean(mut b) => std::sync::Arc::new(b.finish()),
+            Self::Int64(mut b) => std::sync::Arc::new(b.finish()),
+            Self::Float64(mut b) => std::sync::Arc::new(b.finish()),
+            Self::Utf8(mut b) => std::sync::Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Like `query_sql`, but returns the result set as Apache Arrow IPC
+/// (stream format) bytes, base64-encoded, instead of a JSON array of
+/// per-row objects. Intended for analytic queries with many rows, where
+/// building a `serde_json::Map` per row is the bottleneck and the frontend
+/// can hand the bytes straight to an Arrow-aware table/plotting library.
+#[tauri::command]
+pub async fn query_sql_arrow(
+    state: tauri::State<'_, DbState>,
+    sql: String,
+    params: Vec<Value>,
+    tx_id: Option<TxId>,
+) -> Result<String, String> {
+    println!("[Rust DB] Querying SQL (Arrow): {}", sql);
+    let mut handle = resolve_client(&state, &tx_id).await?;
+    let client = handle.client()?;
+
+    let mut processed_sql = sql.clone();
+    for i in 1..=params.len() {
+        processed_sql = processed_sql.replacen('?', &format!("@p{}", i), 1);
+    }
+
+    let sql_params: Vec<Box<dyn ToSql + Sync>> = params.iter().map(json_to_sql_param).collect();
+    let ref_params: Vec<&dyn ToSql> = sql_params.iter().map(|p| p.as_ref() as &dyn ToSql).collect();
+
+    let mut stream = client.query(processed_sql, &ref_params).await.map_err(|e| {
+        println!("[Rust DB] Query error: {}", e);
+        e.to_string()
+    })?;
+
+    let mut column_names: Vec<String> = Vec::new();
+    let mut builders: Option<Vec<ArrowColumnBuilder>> = None;
+    let mut row_count = 0usize;
 
     while let Some(item) = stream.try_next().await.map_err(|e| {
         println!("[Rust DB] Stream error: {}", e);
         e.to_string()
     })? {
         match item {
+            // tiberius surfaces column metadata independent of row count, so
+            // a query that matches zero rows still gets a correctly-shaped
+            // schema instead of an empty one inferred from a row that never
+            // came.
+            // Only the first result set's metadata becomes the schema — this
+            // command returns one `RecordBatch`, so a later `Metadata` (a
+            // multi-statement batch's second `SELECT`) must not reset the
+            // builders and silently drop every row collected so far.
+            QueryItem::Metadata(meta) if builders.is_none() => {
+                column_names = meta.columns().iter().map(|c| c.name().to_string()).collect();
+                builders = Some(meta.columns().iter().map(|c| ArrowColumnBuilder::for_column_type(&c.column_type())).collect());
+            }
+            QueryItem::Metadata(_) => {}
             QueryItem::Row(row) => {
-                let mut map = Map::new();
-                let col_names: Vec<String> = row.columns().iter().map(|c| c.name().to_string()).collect();
-                for (name, data) in col_names.into_iter().zip(row.into_iter()) {
-                    map.insert(name, column_to_json(data));
+                // Fall back to sampling the first row if metadata wasn't
+                // seen ahead of it, so this keeps working even if that
+                // invariant ever changes upstream.
+                if column_names.is_empty() {
+                    column_names = row.columns().iter().map(|c| c.name().to_string()).collect();
                 }
-                rows.push(Value::Object(map));
-            },
-            _ => {}
+                let values: Vec<ColumnData<'_>> = row.into_iter().collect();
+                let builders = builders.get_or_insert_with(|| {
+                    values.iter().map(ArrowColumnBuilder::for_column).collect()
+                });
+                for (builder, value) in builders.iter_mut().zip(values) {
+                    builder.append(value);
+                }
+                row_count += 1;
+            }
         }
     }
-    
-    println!("[Rust DB] Rows returned: {}", rows.len());
-    Ok(Value::Array(rows))
+
+    let fields: Vec<arrow::datatypes::Field> = match &builders {
+        Some(builders) => column_names
+            .iter()
+            .zip(builders)
+            .map(|(name, b)| arrow::datatypes::Field::new(name, b.data_type(), true))
+            .collect(),
+        None => Vec::new(),
+    };
+    let schema = std::sync::Arc::new(arrow::datatypes::Schema::new(fields));
+
+    let batch = match builders {
+        Some(builders) => {
+            let arrays: Vec<arrow::array::ArrayRef> = builders.into_iter().map(ArrowColumnBuilder::finish).collect();
+            arrow::record_batch::RecordBatch::try_new(schema.clone(), arrays).map_err(|e| e.to_string())?
+        }
+        None => arrow::record_batch::RecordBatch::new_empty(schema.clone()),
+    };
+
+    let mut ipc_bytes = Vec::new();
+    {
+        let mut writer = arrow::ipc::writer::StreamWriter::try_new(&mut ipc_bytes, &schema).map_err(|e| e.to_string())?;
+        writer.write(&batch).map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+    }
+
+    println!("[Rust DB] Arrow-encoded {} rows ({} bytes)", row_count, ipc_bytes.len());
+    Ok(base64::engine::general_purpose::STANDARD.encode(ipc_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiberius::numeric::Numeric;
+    use tiberius::time::{Date, DateTime, DateTime2, DateTimeOffset, SmallDateTime, Time};
+
+    /// Checks how `column_to_json` renders numeric, datetime2, date, time and
+    /// guid columns: each comes out as a string with no loss of precision.
+    /// The write side (`json_to_sql_param`) is covered by the round-trip test
+    /// below.
+    #[test]
+    fn column_to_json_renders_numeric_temporal_and_guid_columns() {
+        assert_eq!(
+            column_to_json(ColumnData::Numeric(Some(Numeric::new_with_scale(123456, 2)))),
+            Value::String("1234.56".to_string()),
+        );
+
+        let naive = chrono::NaiveDate::from_ymd_opt(2024, 3, 15)
+            .unwrap()
+            .and_hms_micro_opt(13, 45, 0, 500_000)
+            .unwrap();
+        assert_eq!(
+            column_to_json(ColumnData::DateTime2(Some(DateTime2::from(naive)))),
+            Value::String("2024-03-15T13:45:00.500000".to_string()),
+        );
+
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert_eq!(
+            column_to_json(ColumnData::Date(Some(Date::from(date)))),
+            Value::String("2024-03-15".to_string()),
+        );
+
+        let time = chrono::NaiveTime::from_hms_micro_opt(13, 45, 0, 500_000).unwrap();
+        assert_eq!(
+            column_to_json(ColumnData::Time(Some(Time::from(time)))),
+            Value::String("13:45:00.500000".to_string()),
+        );
+
+        let guid = uuid::Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap();
+        assert_eq!(
+            column_to_json(ColumnData::Guid(Some(guid))),
+            Value::String("550e8400-e29b-41d4-a716-446655440000".to_string()),
+        );
+    }
+
+    #[test]
+    fn json_to_sql_param_detects_guid_and_datetime_strings() {
+        assert!(looks_like_guid("550e8400-e29b-41d4-a716-446655440000"));
+        assert!(!looks_like_guid("not-a-guid"));
+
+        assert!(looks_like_iso_datetime("2024-03-15T13:45:00.500000"));
+        assert!(looks_like_iso_datetime("2024-03-15T13:45:00Z"));
+        assert!(!looks_like_iso_datetime("hello world"));
+    }
+
+    /// Binds each JSON value via `json_to_sql_param` and reads the
+    /// `ColumnData` it produces back through `column_to_json`, asserting the
+    /// value comes out unchanged. Covers the regression where an ordinary
+    /// string param (name below) fell through the guid/datetime guards
+    /// straight to SQL `NULL`.
+    #[test]
+    fn json_to_sql_param_round_trips_through_column_to_json() {
+        let cases = vec![
+            Value::String("Ada Lovelace".to_string()),
+            Value::String("ada@example.com".to_string()),
+            Value::String("550e8400-e29b-41d4-a716-446655440000".to_string()),
+            Value::String("2024-03-15T13:45:00.500000".to_string()),
+        ];
+
+        for value in cases {
+            let bound = json_to_sql_param(&value);
+            let round_tripped = column_to_json(bound.to_sql());
+            assert_eq!(round_tripped, value, "round trip changed {value:?}");
+        }
+    }
+
+    /// A GUID-shaped order number would otherwise be silently coerced to
+    /// `Uuid` by the `looks_like_guid` heuristic; the explicit `"string"`
+    /// type hint is the documented way to force plain-text binding instead.
+    #[test]
+    fn json_to_sql_param_string_hint_bypasses_guid_heuristic() {
+        let order_number = "550e8400-e29b-41d4-a716-446655440000";
+        let hinted = serde_json::json!({"type": "string", "value": order_number});
+
+        let bound = json_to_sql_param(&hinted);
+        let round_tripped = column_to_json(bound.to_sql());
+        assert_eq!(round_tripped, Value::String(order_number.to_string()));
+    }
+
+    /// `0` from the frontend must not turn into a batch that's never
+    /// flushed until the whole result set is buffered — exactly the
+    /// unbounded-memory failure mode `query_sql_stream` exists to avoid.
+    #[test]
+    fn normalize_batch_size_floors_at_one_and_has_a_default() {
+        assert_eq!(normalize_batch_size(None), DEFAULT_STREAM_BATCH_SIZE);
+        assert_eq!(normalize_batch_size(Some(0)), 1);
+        assert_eq!(normalize_batch_size(Some(50)), 50);
+    }
+
+    /// `bb8::Pool::builder().build(..)` doesn't eagerly connect, so
+    /// `DbState::new` succeeds even against an address nothing is listening
+    /// on; the failure only surfaces once something actually checks out a
+    /// connection via `DbState::get`. Pins that contract down, since it's
+    /// the reason `run()`'s setup can construct `DbState` synchronously
+    /// without the app hanging on startup, and the reason a bad profile
+    /// fails at first query instead of at launch.
+    #[tokio::test]
+    async fn db_state_pool_construction_is_lazy_but_get_surfaces_connect_errors() {
+        let mut config = Config::new();
+        config.host("127.0.0.1");
+        config.port(1); // nothing listens here
+        config.authentication(AuthMethod::sql_server("user", "pass"));
+
+        let state = DbState::new(config, 1, Duration::from_secs(1))
+            .await
+            .expect("pool construction shouldn't require a live connection");
+
+        assert!(state.get().await.is_err(), "checking out a connection to a closed port should fail, not hang or panic");
+    }
+
+    fn untouched_slot(idle_for: Duration) -> TransactionSlot {
+        std::sync::Arc::new(tokio::sync::Mutex::new(TransactionHandle {
+            client: None,
+            last_used: std::time::Instant::now() - idle_for,
+        }))
+    }
+
+    /// Exercises the eviction mechanism from the "abandoned transaction wedges
+    /// the pool forever" bug: a slot nobody has touched in longer than the TTL
+    /// gets removed from the map, while a freshly-touched one is left alone.
+    #[tokio::test]
+    async fn reap_stale_transactions_evicts_only_idle_entries() {
+        let ttl = Duration::from_millis(50);
+        let transactions: TransactionMap = std::sync::Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        transactions
+            .lock()
+            .await
+            .insert("stale".to_string(), untouched_slot(Duration::from_secs(1)));
+        transactions
+            .lock()
+            .await
+            .insert("fresh".to_string(), untouched_slot(Duration::from_millis(0)));
+
+        reap_stale_transactions(&transactions, ttl).await;
+
+        let map = transactions.lock().await;
+        assert!(!map.contains_key("stale"), "idle transaction should have been reaped");
+        assert!(map.contains_key("fresh"), "recently-touched transaction should survive the sweep");
+    }
+
+    /// A slot that's locked (i.e. a statement is in flight on it) is never
+    /// reaped even past the TTL — being open a long time isn't the same as
+    /// being abandoned.
+    #[tokio::test]
+    async fn reap_stale_transactions_skips_locked_entries() {
+        let transactions: TransactionMap = std::sync::Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let slot = untouched_slot(Duration::from_secs(10));
+        transactions.lock().await.insert("in_flight".to_string(), slot.clone());
+
+        let _guard = slot.lock().await;
+        reap_stale_transactions(&transactions, Duration::from_millis(1)).await;
+        drop(_guard);
+
+        assert!(transactions.lock().await.contains_key("in_flight"));
+    }
+
+    /// Regression test for the bug where pinning a transaction held the
+    /// *entire* `DbState::transactions` map mutex for the duration of a
+    /// query, serializing every transaction in the app. Two separate
+    /// `tx_id`s locking their own slots concurrently should finish in about
+    /// the time of one sleep, not two.
+    #[tokio::test]
+    async fn concurrent_transactions_on_different_tx_ids_do_not_block_each_other() {
+        let slot_a: TransactionSlot = std::sync::Arc::new(tokio::sync::Mutex::new(TransactionHandle {
+            client: None,
+            last_used: std::time::Instant::now(),
+        }));
+        let slot_b: TransactionSlot = std::sync::Arc::new(tokio::sync::Mutex::new(TransactionHandle {
+            client: None,
+            last_used: std::time::Instant::now(),
+        }));
+
+        let hold = Duration::from_millis(100);
+        let start = std::time::Instant::now();
+        let (a, b) = tokio::join!(
+            async {
+                let _guard = slot_a.lock_owned().await;
+                tokio::time::sleep(hold).await;
+            },
+            async {
+                let _guard = slot_b.lock_owned().await;
+                tokio::time::sleep(hold).await;
+            },
+        );
+        let _ = (a, b);
+
+        assert!(
+            start.elapsed() < hold * 2,
+            "locking two different transaction slots should run concurrently, not serialize"
+        );
+    }
+
+    /// Regression test for the bug where a zero-row result produced an empty
+    /// Arrow schema because column types were only ever inferred from the
+    /// first row. `ArrowColumnBuilder::for_column_type` is what `query_sql_arrow`
+    /// now builds the schema from on seeing `QueryItem::Metadata`, independent
+    /// of whether any `QueryItem::Row` ever arrives.
+    #[test]
+    fn query_sql_arrow_schema_from_metadata_survives_zero_rows() {
+        let column_names = vec!["id".to_string(), "amount".to_string(), "label".to_string()];
+        let builders: Vec<ArrowColumnBuilder> = vec![
+            ArrowColumnBuilder::for_column_type(&ColumnType::Intn),
+            ArrowColumnBuilder::for_column_type(&ColumnType::Floatn),
+            ArrowColumnBuilder::for_column_type(&ColumnType::BigVarChar),
+        ];
+
+        let fields: Vec<arrow::datatypes::Field> = column_names
+            .iter()
+            .zip(&builders)
+            .map(|(name, b)| arrow::datatypes::Field::new(name, b.data_type(), true))
+            .collect();
+        let schema = std::sync::Arc::new(arrow::datatypes::Schema::new(fields));
+        let batch = arrow::record_batch::RecordBatch::new_empty(schema.clone());
+
+        assert_eq!(batch.num_rows(), 0);
+        assert_eq!(schema.fields().len(), 3, "zero rows should not collapse the schema to no columns");
+        assert_eq!(schema.field(0).name(), "id");
+        assert_eq!(*schema.field(0).data_type(), arrow::datatypes::DataType::Int64);
+        assert_eq!(*schema.field(1).data_type(), arrow::datatypes::DataType::Float64);
+        assert_eq!(*schema.field(2).data_type(), arrow::datatypes::DataType::Utf8);
+    }
 }
@@ -1,29 +1,75 @@
+mod connections;
 mod db;
 
-use db::{execute_sql, query_sql, DbState};
-use tiberius::{Config, AuthMethod};
+use std::time::Duration;
+
+use connections::{
+    add_connection, list_connections, remove_connection, set_active_connection, config_for_profile,
+    ConnectionStore,
+};
+use db::{
+    begin_transaction, commit_transaction, execute_sql, query_sql, query_sql_arrow,
+    query_sql_stream, rollback_transaction, DbState,
+};
+use tauri::Manager;
 
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+const DEFAULT_POOL_SIZE: u32 = 10;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let mut config = Config::new();
-    config.host("sql-db-a.database.windows.net");
-    config.port(1433);
-    config.database("free-sql-db-3095376");
-    config.authentication(AuthMethod::sql_server("CloudSA271ce787@sql-db-a", "Ashutosh@123"));
-    config.encryption(tiberius::EncryptionLevel::Required);
-    config.trust_cert(); // Azure usually needs this or a proper bundle
-
     tauri::Builder::default()
-        .manage(DbState { config })
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![greet, execute_sql, query_sql])
+        .setup(|app| {
+            let app_dir = app.path().app_data_dir().expect("no app data dir");
+            std::fs::create_dir_all(&app_dir)?;
+
+            let store = ConnectionStore::open(&app_dir.join("profiles.db"))?;
+
+            let profile = store
+                .active_id()
+                .ok()
+                .flatten()
+                .and_then(|id| store.get(&id).ok().flatten())
+                .ok_or_else(|| {
+                    "no active connection profile configured — use add_connection and \
+                     set_active_connection before launching"
+                        .to_string()
+                })?;
+            let config = config_for_profile(&profile)?;
+
+            let db_state = tauri::async_runtime::block_on(DbState::new(
+                config,
+                DEFAULT_POOL_SIZE,
+                DEFAULT_IDLE_TIMEOUT,
+            ))
+            .expect("failed to initialize database connection pool");
+
+            app.manage(store);
+            app.manage(db_state);
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            execute_sql,
+            query_sql,
+            query_sql_stream,
+            query_sql_arrow,
+            begin_transaction,
+            commit_transaction,
+            rollback_transaction,
+            add_connection,
+            list_connections,
+            remove_connection,
+            set_active_connection,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }